@@ -1,24 +1,115 @@
-use support::{decl_storage, decl_module, decl_event, ensure, dispatch::Result,
-    StorageValue, StorageMap, traits::Currency};
+use support::{decl_storage, decl_module, decl_event, ensure, dispatch::Result, Parameter,
+    StorageValue, StorageMap,
+    traits::{Currency, ReservableCurrency, Get}};
 use system::ensure_signed;
 use runtime_primitives::traits::{As, Hash, Zero};
 use parity_codec::{Encode, Decode};
 use rstd::cmp;
-//use itertools::izip;
+use rstd::vec::Vec;
 
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Chocobo<Hash, Balance> {
+pub struct Chocobo<Hash, Balance, AssetId> {
     id: Hash,
     dna: Hash,
     price: Balance,
+    /// The asset the price is quoted in; `None` means the native currency.
+    price_asset: Option<AssetId>,
     gen: u64,
     wins: u64,
     races: u64,
+    rating: u32,
 }
 
+/// On-chain randomness source. Defined locally because this Substrate version
+/// predates `support::traits::Randomness`; a runtime wires in its collective
+/// flip / babe output here.
+pub trait Randomness<Output> {
+    /// A random value seeded by `subject`, deterministic on-chain yet
+    /// unpredictable to the caller.
+    fn random(subject: &[u8]) -> Output;
+}
+
+/// Every chocobo starts at this rating, the conventional Elo seed.
+const STARTING_RATING: u32 = 1200;
+/// Ratings never fall below this floor.
+const RATING_FLOOR: u32 = 100;
+/// The Elo K-factor governing how quickly ratings move.
+const ELO_K: i64 = 32;
+
+/// The smallest and largest co-owner sets a chocobo may have, mirroring the
+/// reference space-owners pallet's MIN/MAX bounds.
+const MIN_OWNERS: usize = 2;
+const MAX_OWNERS: usize = 100;
+
+/// An action proposed by the co-owners of a chocobo, executed once the asset's
+/// approval threshold is reached.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum ChocoboAction<AccountId, Balance, AssetId> {
+    /// Move the chocobo to `AccountId` and dissolve the co-ownership.
+    Transfer(AccountId),
+    /// Set the chocobo's sale price to `Balance`, quoted in the given asset
+    /// (`None` for the native currency).
+    SetPrice(Option<AssetId>, Balance),
+    /// List the chocobo for sale at `Balance` in the given asset, letting an
+    /// external buyer settle the purchase through `buy_chocobo`.
+    Sale(Option<AssetId>, Balance),
+}
+
+/// A generic enumerable NFT surface, modeled on pallet-commodities, that other
+/// pallets can reuse to query and move unique assets without reaching into the
+/// chocobo storage maps directly.
+pub trait UniqueAssets<AccountId> {
+    /// The identifier that uniquely addresses a single asset.
+    type AssetId;
+    /// The payload stored alongside each asset.
+    type AssetInfo;
+
+    /// The total number of assets in existence.
+    fn total() -> u64;
+    /// The number of assets owned by `who`.
+    fn total_for_account(who: &AccountId) -> u64;
+    /// The identifiers of every asset owned by `who`.
+    fn assets_for_account(who: &AccountId) -> Vec<Self::AssetId>;
+    /// The current owner of `id`, if it exists.
+    fn owner_of(id: &Self::AssetId) -> Option<AccountId>;
+    /// Create `info` and assign it to `who`.
+    fn mint(who: &AccountId, info: Self::AssetInfo) -> Result;
+    /// Destroy `id`, removing it from every index.
+    fn burn(id: &Self::AssetId) -> Result;
+    /// Move `id` to `dest`.
+    fn transfer(dest: &AccountId, id: &Self::AssetId) -> Result;
+}
+
+/// Minimal fungible-asset transfer surface this pallet needs. Defined locally
+/// because this Substrate version predates `support::traits::fungibles`; a
+/// runtime backs it with its assets pallet.
+pub trait Fungibles<AccountId> {
+    /// Identifier of a registered asset.
+    type AssetId;
+    /// The balance type those assets are denominated in.
+    type Balance;
+    /// Move `amount` of `asset` from `source` to `dest`.
+    fn transfer(asset: Self::AssetId, source: &AccountId, dest: &AccountId,
+        amount: Self::Balance, keep_alive: bool) -> Result;
+}
+
+type BalanceOf<T> =
+    <balances::Module<T> as Currency<<T as system::Trait>::AccountId>>::Balance;
+
 pub trait Trait: balances::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    type RandomnessSource: Randomness<Self::Hash>;
+    /// The amount reserved from the caller when a chocobo is minted or bred. The
+    /// reserve is handed over to the buyer on a sale and refunded to the account
+    /// holding it when the chocobo is burned. A plain or co-owner-approved
+    /// transfer does not move it: it stays with the account that reserved it.
+    type ChocoboDeposit: Get<BalanceOf<Self>>;
+    /// Identifier of a registered on-chain asset a chocobo may be priced in.
+    type AssetId: Parameter + Copy + Default;
+    /// The fungibles handle used to settle non-native sales.
+    type Assets: Fungibles<Self::AccountId, AssetId = Self::AssetId, Balance = BalanceOf<Self>>;
 }
 
 decl_event!(
@@ -27,19 +118,24 @@ decl_event!(
         <T as system::Trait>::AccountId,
         <T as system::Trait>::Hash,
         <T as balances::Trait>::Balance,
+        <T as Trait>::AssetId,
     {
         Created(AccountId, Hash),
         PriceSet(AccountId, Hash, Balance),
         Transferred(AccountId, AccountId, Hash),
-        Bought(AccountId, AccountId, Hash, Balance),
+        Bought(AccountId, AccountId, Hash, Option<AssetId>, Balance),
         Bred(AccountId, Hash, Hash, Hash),
         Raced(AccountId, Hash, Hash, Hash),
+        Burned(AccountId, Hash),
+        CoOwnersSet(Hash),
+        ActionProposed(AccountId, Hash, Hash),
+        ActionApproved(AccountId, Hash, Hash),
     }
 );
 
 decl_storage! {
     trait Store for Module<T: Trait> as ChocoboStorage {
-        Chocobos get(choco_by_id): map T::Hash => Chocobo<T::Hash, T::Balance>;
+        Chocobos get(choco_by_id): map T::Hash => Chocobo<T::Hash, T::Balance, T::AssetId>;
         Owners get(owner_of): map T::Hash => Option<T::AccountId>;
 
         AllChocobosArray get(choco_by_index): map u64 => T::Hash;
@@ -49,6 +145,25 @@ decl_storage! {
         OwnedChocobosArray get(choco_of_owner_by_index): map (T::AccountId, u64) => T::Hash;
         OwnedChocobosCount get(count_by_account): map T::AccountId => u64;
         OwnedChocobosIndex: map T::Hash => u64;
+
+        // The account currently holding the reserved deposit for each chocobo,
+        // and the reserved amount. Moved to the buyer on sale and released back
+        // to the holding account on burn; a transfer leaves it unchanged, so the
+        // holder need not be the current owner.
+        Deposits get(deposit_of): map T::Hash => (T::AccountId, BalanceOf<T>);
+
+        CoOwners get(co_owners): map T::Hash => Vec<T::AccountId>;
+        Threshold get(threshold_of): map T::Hash => u16;
+        PendingTransfers get(pending_action):
+            map (T::Hash, T::Hash) =>
+                Option<(ChocoboAction<T::AccountId, T::Balance, T::AssetId>, Vec<T::AccountId>)>;
+        AssetProposals get(proposals_of): map T::Hash => Vec<T::Hash>;
+        ForSale get(is_for_sale): map T::Hash => bool;
+
+        /// Optional conversion rate, in per-mille, from a quoted asset (the key's
+        /// first element) to a payment asset (the second), letting a buyer settle
+        /// in one asset while the seller quotes in another.
+        ConversionRate get(conversion_rate): map (T::AssetId, T::AssetId) => Option<u128>;
         Nonce: u64;
     }
 }
@@ -62,7 +177,7 @@ decl_module! {
             let sender = ensure_signed(origin)?;
 
             let nonce = <Nonce<T>>::get();
-            let random_seed = <system::Module<T>>::random_seed();
+            let random_seed = T::RandomnessSource::random(b"create");
             let random_hash = (random_seed, &sender, nonce)
                 .using_encoded(<T as system::Trait>::Hashing::hash);
 
@@ -70,25 +185,98 @@ decl_module! {
                 id: random_hash,
                 dna: random_hash,
                 price: <T::Balance as As<u64>>::sa(0),
+                price_asset: None,
                 gen: 0,
                 wins: 0,
                 races: 0,
+                rating: STARTING_RATING,
             };
 
-            Self::mint(sender, random_hash, new_choco)?;
+            let deposit = T::ChocoboDeposit::get();
+            <balances::Module<T> as ReservableCurrency<_>>::reserve(&sender, deposit)?;
+
+            <Self as UniqueAssets<T::AccountId>>::mint(&sender, new_choco)?;
+            <Deposits<T>>::insert(random_hash, (sender.clone(), deposit));
             <Nonce<T>>::mutate(|n| *n += 1);
             Ok(())
         }
 
-        fn set_price(origin, choco_id: T::Hash, new_price: T::Balance) -> Result {
+        fn burn_chocobo(origin, choco_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<Chocobos<T>>::exists(choco_id), "This chocobo does not exist");
+
+            let owner = Self::owner_of(choco_id).ok_or("No owner for this chocobo")?;
+            ensure!(owner == sender, "You do not own this chocobo");
+            ensure!(Self::co_owners(choco_id).is_empty(),
+                "This chocobo is co-owned; use propose/approve");
+
+            <Self as UniqueAssets<T::AccountId>>::burn(&choco_id)?;
+
+            Self::deposit_event(RawEvent::Burned(sender, choco_id));
+            Ok(())
+        }
+
+        fn set_co_owners(origin, choco_id: T::Hash, owners: Vec<T::AccountId>, threshold: u16) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<Chocobos<T>>::exists(choco_id), "This chocobo does not exist");
+
+            let owner = Self::owner_of(choco_id).ok_or("No owner for this chocobo")?;
+            ensure!(owner == sender, "You do not own this chocobo");
+
+            ensure!(owners.len() >= MIN_OWNERS, "Too few co-owners");
+            ensure!(owners.len() <= MAX_OWNERS, "Too many co-owners");
+            ensure!(threshold >= 1, "Threshold must be at least 1");
+            ensure!(threshold as usize <= owners.len(), "Threshold exceeds the number of co-owners");
+
+            <CoOwners<T>>::insert(choco_id, owners);
+            <Threshold<T>>::insert(choco_id, threshold);
+
+            Self::deposit_event(RawEvent::CoOwnersSet(choco_id));
+            Ok(())
+        }
+
+        fn propose_transfer(origin, choco_id: T::Hash, to: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::propose_action(sender, choco_id, ChocoboAction::Transfer(to))
+        }
+
+        fn approve_transfer(origin, choco_id: T::Hash, proposal: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::approve_action(sender, choco_id, proposal)
+        }
+
+        fn propose_set_price(origin, choco_id: T::Hash, asset_id: Option<T::AssetId>, new_price: T::Balance) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::propose_action(sender, choco_id, ChocoboAction::SetPrice(asset_id, new_price))
+        }
+
+        fn approve_set_price(origin, choco_id: T::Hash, proposal: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::approve_action(sender, choco_id, proposal)
+        }
+
+        fn propose_sale(origin, choco_id: T::Hash, asset_id: Option<T::AssetId>, new_price: T::Balance) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::propose_action(sender, choco_id, ChocoboAction::Sale(asset_id, new_price))
+        }
+
+        fn approve_sale(origin, choco_id: T::Hash, proposal: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            Self::approve_action(sender, choco_id, proposal)
+        }
+
+        fn set_price(origin, choco_id: T::Hash, asset_id: Option<T::AssetId>, new_price: T::Balance) -> Result {
             let sender = ensure_signed(origin)?;
             ensure!(<Chocobos<T>>::exists(choco_id), "This choco does not exist");
 
             let owner = Self::owner_of(choco_id).ok_or("No owner for this chocobo")?;
             ensure!(owner == sender, "You do not own this chocobo");
+            ensure!(Self::co_owners(choco_id).is_empty(),
+                "This chocobo is co-owned; use propose/approve");
 
             let mut choco = Self::choco_by_id(choco_id);
             choco.price = new_price;
+            choco.price_asset = asset_id;
 
             <Chocobos<T>>::insert(choco_id, choco);
 
@@ -101,26 +289,62 @@ decl_module! {
 
             let owner = Self::owner_of(choco_id).ok_or("No owner of this chocobo")?;
             ensure!(owner == sender, "You do not own this chocobo");
+            ensure!(Self::co_owners(choco_id).is_empty(),
+                "This chocobo is co-owned; use propose/approve");
 
-            Self::transfer_from(sender, to, choco_id)?;
+            <Self as UniqueAssets<T::AccountId>>::transfer(&to, &choco_id)?;
 
             Ok(())
         }
 
-        fn buy_chocobo(origin, choco_id: T::Hash, max_price: T::Balance) -> Result {
+        fn buy_chocobo(origin, choco_id: T::Hash, pay_asset: Option<T::AssetId>, max_price: T::Balance) -> Result {
             let sender = ensure_signed(origin)?;
             ensure!(<Chocobos<T>>::exists(choco_id), "This chocobo does not exist");
 
             let owner = Self::owner_of(choco_id).ok_or("No owner for this chocobo")?;
             ensure!(owner != sender, "You already own this chocobo");
+            // co-owned chocobos can only be bought once the owners have
+            // threshold-approved a sale (see `propose_sale`)
+            let co_owned = !Self::co_owners(choco_id).is_empty();
+            if co_owned {
+                ensure!(Self::is_for_sale(choco_id),
+                    "This chocobo is co-owned; owners must approve a sale first");
+            }
 
             let mut choco = Self::choco_by_id(choco_id);
             let price = choco.price;
             ensure!(!price.is_zero(), "The chocobo you want is not for sale");
-            ensure!(price <= max_price, "The chocobo you want costs more than your max price");
 
-            <balances::Module<T> as Currency<_>>::transfer(&sender, &owner, price)?;
-            Self::transfer_from(owner.clone(), sender.clone(), choco_id)
+            // work out the asset and amount actually charged, converting to the
+            // buyer's chosen asset when a rate is registered
+            let (settle, amount) = match choco.price_asset {
+                None => (None, price),
+                Some(quote) => {
+                    let settle = pay_asset.unwrap_or(quote);
+                    let amount = if settle == quote {
+                        price
+                    } else {
+                        let rate = Self::conversion_rate((quote, settle))
+                            .ok_or("No conversion rate for the chosen payment asset")?;
+                        Self::convert(price, rate)
+                    };
+                    (Some(settle), amount)
+                },
+            };
+
+            // slippage guard against the amount the buyer really pays
+            ensure!(amount <= max_price, "The chocobo you want costs more than your max price");
+
+            match settle {
+                None => {
+                    <balances::Module<T> as Currency<_>>::transfer(&sender, &owner, amount)?;
+                },
+                Some(asset) => {
+                    T::Assets::transfer(asset, &sender, &owner, amount, false)?;
+                },
+            }
+
+            <Self as UniqueAssets<T::AccountId>>::transfer(&sender, &choco_id)
                 .expect("`owner` shown to own chocobo; \
                          `owner` has at least 1 kitten so transfer cannot underflow; \
                          `owner_count` shares type with `all_count` \
@@ -128,10 +352,27 @@ decl_module! {
                          so transfer cannot overflow; \
                          qed");
 
+            // a completed sale dissolves any co-ownership and clears its listing
+            if co_owned {
+                <CoOwners<T>>::remove(choco_id);
+                <Threshold<T>>::remove(choco_id);
+                <ForSale<T>>::remove(choco_id);
+                Self::clear_proposals(choco_id);
+            }
+
+            // hand the reserved deposit over to the buyer so the account that
+            // held it reclaims its funds and the new owner carries the stake
+            let deposit = T::ChocoboDeposit::get();
+            <balances::Module<T> as ReservableCurrency<_>>::reserve(&sender, deposit)?;
+            let (reserver, reserved) = <Deposits<T>>::get(choco_id);
+            <balances::Module<T> as ReservableCurrency<_>>::unreserve(&reserver, reserved);
+            <Deposits<T>>::insert(choco_id, (sender.clone(), deposit));
+
             choco.price = <T::Balance as As<u64>>::sa(0);
+            choco.price_asset = None;
             <Chocobos<T>>::insert(choco_id, choco);
 
-            Self::deposit_event(RawEvent::Bought(sender, owner, choco_id, price));
+            Self::deposit_event(RawEvent::Bought(sender, owner, choco_id, settle, amount));
             Ok(())
         }
 
@@ -141,7 +382,7 @@ decl_module! {
             ensure!(<Chocobos<T>>::exists(mare_id), "Mare chocobo does not exist");
 
             let nonce = <Nonce<T>>::get();
-            let random_seed = <system::Module<T>>::random_seed();
+            let random_seed = T::RandomnessSource::random(b"breed");
             let random_hash = (random_seed, &sender, nonce)
                 .using_encoded(<T as system::Trait>::Hashing::hash);
 
@@ -160,12 +401,18 @@ decl_module! {
                 id: random_hash,
                 dna: child_dna,
                 price: <T::Balance as As<u64>>::sa(0),
+                price_asset: None,
                 gen: cmp::max(sire.gen, mare.gen) + 1,
                 wins: 0,
                 races: 0,
+                rating: STARTING_RATING,
             };
 
-            Self::mint(sender.clone(), random_hash, new_choco)?;
+            let deposit = T::ChocoboDeposit::get();
+            <balances::Module<T> as ReservableCurrency<_>>::reserve(&sender, deposit)?;
+
+            <Self as UniqueAssets<T::AccountId>>::mint(&sender, new_choco)?;
+            <Deposits<T>>::insert(random_hash, (sender.clone(), deposit));
             <Nonce<T>>::mutate(|n| *n += 1);
             Self::deposit_event(RawEvent::Bred(sender, sire_id, mare_id, random_hash));
             Ok(())
@@ -176,35 +423,61 @@ decl_module! {
             ensure!(<Chocobos<T>>::exists(choco1_id), "Chocobo contender1 does not exist");
             ensure!(<Chocobos<T>>::exists(choco2_id), "Chocobo contender1 does not exist");
 
-            //let nonce = <Nonce<T>>::get();
-            //let random_seed = <system::Module<T>>::random_seed();
-            //let random_hash = (random_seed, &sender, nonce)
-            //    .using_encoded(<T as system::Trait>::Hashing::hash);
+            let nonce = <Nonce<T>>::get();
+            let random_seed = T::RandomnessSource::random(b"race");
 
             let mut choco1 = Self::choco_by_id(choco1_id);
-            let dna1 = choco1.dna.as_ref().iter();
             let mut choco2 = Self::choco_by_id(choco2_id);
+
+            let random_hash = (random_seed, &sender, nonce, choco1.dna, choco2.dna)
+                .using_encoded(<T as system::Trait>::Hashing::hash);
+
+            let dna1 = choco1.dna.as_ref().iter();
             let dna2 = choco2.dna.as_ref().iter();
+            let rand = random_hash.as_ref().iter();
 
             let mut winner = choco1_id;
-            let mut outcome = 0;
-            //for (gt1, gt2, rand) in izip!(dna1, dna2, random_hash) {
-            for (gt1, gt2) in dna1.zip(dna2) {
-                if gt1 >= gt2 {
-                    outcome += 1;
+            let mut outcome: i32 = 0;
+            for ((gt1, gt2), r) in dna1.zip(dna2).zip(rand) {
+                // only ~half the genotype positions are "live" each race
+                if r & 1 == 1 {
+                    if gt1 >= gt2 {
+                        outcome += 1;
+                    } else {
+                        outcome -= 1;
+                    }
+                }
+            }
+
+            // nudge the comparison toward the higher-rated chocobo so the
+            // rating actually shifts win probability
+            outcome += Self::rating_bias(choco1.rating, choco2.rating);
+
+            // break an exact tie with the parity of the final random byte
+            if outcome == 0 {
+                if let Some(last) = random_hash.as_ref().last() {
+                    outcome = if last & 1 == 1 { 1 } else { -1 };
                 } else {
-                    outcome -= 1;
+                    outcome = 1;
                 }
             }
 
             choco1.races += 1; //checked_add
             choco2.races += 1; //checked_add
-            if outcome >= 0 {
+            let choco1_won = outcome >= 0;
+            if choco1_won {
                 choco1.wins += 1; //checked_add
             } else {
                 winner = choco2_id;
                 choco2.wins += 1; //checked_add
             }
+
+            // update both ratings from the result
+            let e1 = Self::expected_score(choco1.rating, choco2.rating);
+            let e2 = Self::expected_score(choco2.rating, choco1.rating);
+            choco1.rating = Self::adjust_rating(choco1.rating, e1, choco1_won);
+            choco2.rating = Self::adjust_rating(choco2.rating, e2, !choco1_won);
+
             <Chocobos<T>>::insert(choco1_id, choco1);
             <Chocobos<T>>::insert(choco2_id, choco2);
 
@@ -216,10 +489,192 @@ decl_module! {
 }
 
 impl<T: Trait> Module<T> {
-    fn mint(to: T::AccountId, choco_id: T::Hash, new_choco: Chocobo<T::Hash, T::Balance>) -> Result {
+    /// Record a new pending action proposed by a co-owner, executing it straight
+    /// away when the asset's threshold is `1`.
+    fn propose_action(
+        sender: T::AccountId,
+        choco_id: T::Hash,
+        action: ChocoboAction<T::AccountId, T::Balance, T::AssetId>,
+    ) -> Result {
+        ensure!(<Chocobos<T>>::exists(choco_id), "This chocobo does not exist");
+        ensure!(Self::co_owners(choco_id).contains(&sender), "You are not a co-owner of this chocobo");
+
+        let nonce = <Nonce<T>>::get();
+        let proposal = (choco_id, &sender, nonce, action.clone())
+            .using_encoded(<T as system::Trait>::Hashing::hash);
+        <Nonce<T>>::mutate(|n| *n += 1);
+
+        let mut approvals = Vec::new();
+        approvals.push(sender.clone());
+        Self::deposit_event(RawEvent::ActionProposed(sender, choco_id, proposal));
+
+        if approvals.len() as u16 >= Self::threshold_of(choco_id) {
+            Self::execute_action(choco_id, action)?;
+        } else {
+            <PendingTransfers<T>>::insert((choco_id, proposal), (action, approvals));
+            <AssetProposals<T>>::mutate(choco_id, |ps| ps.push(proposal));
+        }
+        Ok(())
+    }
+
+    /// Add `sender`'s distinct approval to a pending action and execute it once
+    /// the threshold is reached.
+    fn approve_action(sender: T::AccountId, choco_id: T::Hash, proposal: T::Hash) -> Result {
+        ensure!(Self::co_owners(choco_id).contains(&sender), "You are not a co-owner of this chocobo");
+
+        let (action, mut approvals) = Self::pending_action((choco_id, proposal))
+            .ok_or("No such pending action")?;
+        ensure!(!approvals.contains(&sender), "You have already approved this action");
+
+        approvals.push(sender.clone());
+        Self::deposit_event(RawEvent::ActionApproved(sender, choco_id, proposal));
+
+        if approvals.len() as u16 >= Self::threshold_of(choco_id) {
+            Self::drop_proposal(choco_id, proposal);
+            Self::execute_action(choco_id, action)?;
+        } else {
+            <PendingTransfers<T>>::insert((choco_id, proposal), (action, approvals));
+        }
+        Ok(())
+    }
+
+    /// Forget a single pending proposal, clearing both its entry and its slot in
+    /// the per-asset index.
+    fn drop_proposal(choco_id: T::Hash, proposal: T::Hash) {
+        <PendingTransfers<T>>::remove((choco_id, proposal));
+        <AssetProposals<T>>::mutate(choco_id, |ps| ps.retain(|p| *p != proposal));
+    }
+
+    /// Discard every pending proposal for a chocobo, used when it leaves
+    /// co-ownership.
+    fn clear_proposals(choco_id: T::Hash) {
+        for proposal in <AssetProposals<T>>::get(choco_id) {
+            <PendingTransfers<T>>::remove((choco_id, proposal));
+        }
+        <AssetProposals<T>>::remove(choco_id);
+    }
+
+    /// The current Elo rating of a chocobo.
+    pub fn rating_of(choco_id: T::Hash) -> u32 {
+        Self::choco_by_id(choco_id).rating
+    }
+
+    /// Every chocobo paired with its rating, ordered strongest first, for
+    /// leaderboards and skill-based matchmaking.
+    pub fn leaderboard() -> Vec<(T::Hash, u32)> {
+        let count = Self::get_all_count();
+        let mut board = Vec::new();
+        for i in 0..count {
+            let id = Self::choco_by_index(i);
+            board.push((id, Self::choco_by_id(id).rating));
+        }
+        board.sort_by(|a, b| b.1.cmp(&a.1));
+        board
+    }
+
+    /// The expected score of `own` against `opponent`, in per-mille, computed by
+    /// `1000 / (1 + 10^((R_b - R_a)/400))` with `10^x` read from a clamped table
+    /// over the rating gap (limited to ±800).
+    fn expected_score(own: u32, opponent: u32) -> u32 {
+        let mut diff = opponent as i32 - own as i32;
+        if diff < -800 { diff = -800; }
+        if diff > 800 { diff = 800; }
+        // E_a in per-mille at 100-point buckets of (R_b - R_a), from -800..=800
+        const TABLE: [u32; 17] = [
+            990, 983, 969, 947, 909, 849, 760, 640, 500,
+            360, 240, 151, 91, 53, 31, 17, 10,
+        ];
+        // round to the nearest bucket
+        let bucket = ((diff + 800 + 50) / 100) as usize;
+        let bucket = cmp::min(bucket, TABLE.len() - 1);
+        TABLE[bucket]
+    }
+
+    /// Apply one Elo step: `R' = R + K*(S*1000 - E)/1000`, saturating and never
+    /// dropping below `RATING_FLOOR`.
+    fn adjust_rating(rating: u32, expected: u32, won: bool) -> u32 {
+        let score = if won { 1000i64 } else { 0i64 };
+        let delta = ELO_K * (score - expected as i64) / 1000;
+        let next = rating as i64 + delta;
+        if next < RATING_FLOOR as i64 {
+            RATING_FLOOR
+        } else {
+            next as u32
+        }
+    }
+
+    /// A small integer nudge to the genotype tally favouring the higher-rated
+    /// chocobo, scaled by the rating gap and capped so skill tilts but never
+    /// decides a race outright.
+    fn rating_bias(rating1: u32, rating2: u32) -> i32 {
+        let bias = (rating1 as i32 - rating2 as i32) / 200;
+        cmp::max(-4, cmp::min(4, bias))
+    }
+
+    /// Convert an amount at a per-mille conversion `rate`.
+    fn convert(amount: T::Balance, rate: u128) -> T::Balance {
+        let scaled = <T::Balance as As<u64>>::as_(amount) as u128 * rate / 1000;
+        <T::Balance as As<u64>>::sa(scaled as u64)
+    }
+
+    /// Carry out an approved action atomically.
+    fn execute_action(choco_id: T::Hash, action: ChocoboAction<T::AccountId, T::Balance, T::AssetId>) -> Result {
+        match action {
+            ChocoboAction::Transfer(to) => {
+                <Self as UniqueAssets<T::AccountId>>::transfer(&to, &choco_id)?;
+                <CoOwners<T>>::remove(choco_id);
+                <Threshold<T>>::remove(choco_id);
+                <ForSale<T>>::remove(choco_id);
+                Self::clear_proposals(choco_id);
+            },
+            ChocoboAction::SetPrice(asset_id, new_price) => {
+                let mut choco = Self::choco_by_id(choco_id);
+                choco.price = new_price;
+                choco.price_asset = asset_id;
+                <Chocobos<T>>::insert(choco_id, choco);
+            },
+            ChocoboAction::Sale(asset_id, new_price) => {
+                let mut choco = Self::choco_by_id(choco_id);
+                choco.price = new_price;
+                choco.price_asset = asset_id;
+                <Chocobos<T>>::insert(choco_id, choco);
+                <ForSale<T>>::insert(choco_id, true);
+            },
+        }
+        Ok(())
+    }
+}
+
+impl<T: Trait> UniqueAssets<T::AccountId> for Module<T> {
+    type AssetId = T::Hash;
+    type AssetInfo = Chocobo<T::Hash, T::Balance, T::AssetId>;
+
+    fn total() -> u64 {
+        Self::get_all_count()
+    }
+
+    fn total_for_account(who: &T::AccountId) -> u64 {
+        Self::count_by_account(who)
+    }
+
+    fn assets_for_account(who: &T::AccountId) -> Vec<T::Hash> {
+        let count = Self::count_by_account(who);
+        let mut assets = Vec::new();
+        for i in 0..count {
+            assets.push(Self::choco_of_owner_by_index((who.clone(), i)));
+        }
+        assets
+    }
+
+    fn owner_of(id: &T::Hash) -> Option<T::AccountId> {
+        <Owners<T>>::get(id)
+    }
+
+    fn mint(to: &T::AccountId, new_choco: Chocobo<T::Hash, T::Balance, T::AssetId>) -> Result {
+        let choco_id = new_choco.id;
         ensure!(!<Chocobos<T>>::exists(choco_id), "This new chocobo id already exists");
 
-        let owned_count = Self::count_by_account(&to);
+        let owned_count = Self::count_by_account(to);
         let new_owned_count = owned_count.checked_add(1)
             .ok_or("Overflow adding a new chocobo to account")?;
 
@@ -228,26 +683,68 @@ impl<T: Trait> Module<T> {
             .ok_or("Overflow adding a new chocobo to total")?;
 
         <Chocobos<T>>::insert(choco_id, new_choco);
-        <Owners<T>>::insert(choco_id, &to);
+        <Owners<T>>::insert(choco_id, to);
 
         <AllChocobosArray<T>>::insert(all_count, choco_id);
         <AllChocobosCount<T>>::put(new_count);
         <AllChocobosIndex<T>>::insert(choco_id, all_count);
 
         <OwnedChocobosArray<T>>::insert((to.clone(), owned_count), choco_id);
-        <OwnedChocobosCount<T>>::insert(&to, new_owned_count);
+        <OwnedChocobosCount<T>>::insert(to, new_owned_count);
         <OwnedChocobosIndex<T>>::insert(choco_id, owned_count);
 
-        Self::deposit_event(RawEvent::Created(to, choco_id));
+        Self::deposit_event(RawEvent::Created(to.clone(), choco_id));
+        Ok(())
+    }
+
+    fn burn(choco_id: &T::Hash) -> Result {
+        let owner = Self::owner_of(*choco_id).ok_or("No owner of this chocobo")?;
+
+        let owned_count = Self::count_by_account(&owner);
+        let new_owned_count = owned_count.checked_sub(1)
+            .ok_or("Burn causes underflow of owner account")?;
+
+        let all_count = Self::get_all_count();
+        let new_count = all_count.checked_sub(1)
+            .ok_or("Burn causes underflow of total count")?;
+
+        // swap-and-pop the asset out of the owner's array
+        let choco_index = <OwnedChocobosIndex<T>>::get(choco_id);
+        if choco_index != new_owned_count {
+            let last_choco_id = <OwnedChocobosArray<T>>::get((owner.clone(), new_owned_count));
+            <OwnedChocobosArray<T>>::insert((owner.clone(), choco_index), last_choco_id);
+            <OwnedChocobosIndex<T>>::insert(last_choco_id, choco_index);
+        }
+        <OwnedChocobosArray<T>>::remove((owner.clone(), new_owned_count));
+        <OwnedChocobosCount<T>>::insert(&owner, new_owned_count);
+        <OwnedChocobosIndex<T>>::remove(choco_id);
+
+        // swap-and-pop the asset out of the global array
+        let all_index = <AllChocobosIndex<T>>::get(choco_id);
+        if all_index != new_count {
+            let last_choco_id = <AllChocobosArray<T>>::get(new_count);
+            <AllChocobosArray<T>>::insert(all_index, last_choco_id);
+            <AllChocobosIndex<T>>::insert(last_choco_id, all_index);
+        }
+        <AllChocobosArray<T>>::remove(new_count);
+        <AllChocobosCount<T>>::put(new_count);
+        <AllChocobosIndex<T>>::remove(choco_id);
+
+        let (reserver, deposit) = <Deposits<T>>::get(choco_id);
+        <balances::Module<T> as ReservableCurrency<_>>::unreserve(&reserver, deposit);
+        <Deposits<T>>::remove(choco_id);
+
+        <Chocobos<T>>::remove(choco_id);
+        <Owners<T>>::remove(choco_id);
+
         Ok(())
     }
 
-    fn transfer_from(from: T::AccountId, to: T::AccountId, choco_id: T::Hash) -> Result {
-        let owner = Self::owner_of(choco_id).ok_or("No owner of this chocobo")?;
-        ensure!(owner == from, "You do not own this chocobo");
+    fn transfer(to: &T::AccountId, choco_id: &T::Hash) -> Result {
+        let from = Self::owner_of(*choco_id).ok_or("No owner of this chocobo")?;
 
         let owned_count_from = Self::count_by_account(&from);
-        let owned_count_to = Self::count_by_account(&to);
+        let owned_count_to = Self::count_by_account(to);
         let new_count_from = owned_count_from.checked_sub(1)
             .ok_or("Transfer causes underflow of 'from' account")?;
         let new_count_to = owned_count_to.checked_add(1)
@@ -260,15 +757,15 @@ impl<T: Trait> Module<T> {
             <OwnedChocobosIndex<T>>::insert(last_choco_id, choco_index);
         }
 
-        <Owners<T>>::insert(choco_id, &to);
+        <Owners<T>>::insert(choco_id, to);
         <OwnedChocobosIndex<T>>::insert(choco_id, owned_count_to);
         <OwnedChocobosArray<T>>::remove((from.clone(), new_count_from));
-        <OwnedChocobosArray<T>>::insert((to.clone(), owned_count_to), choco_id); 
+        <OwnedChocobosArray<T>>::insert((to.clone(), owned_count_to), *choco_id);
 
         <OwnedChocobosCount<T>>::insert(&from, new_count_from);
-        <OwnedChocobosCount<T>>::insert(&to, new_count_to);
+        <OwnedChocobosCount<T>>::insert(to, new_count_to);
 
-        Self::deposit_event(RawEvent::Transferred(from, to, choco_id));
+        Self::deposit_event(RawEvent::Transferred(from, to.clone(), *choco_id));
         Ok(())
     }
 }